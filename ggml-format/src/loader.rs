@@ -1,6 +1,6 @@
 use std::{
     error::Error,
-    io::{BufRead, Seek, SeekFrom},
+    io::{self, BufRead, Cursor, Read, Seek, SeekFrom},
 };
 
 use crate::{
@@ -31,6 +31,25 @@ pub enum LoadError<E: Error> {
     /// sanity check failed
     #[error("invariant broken: {0}")]
     InvariantBroken(String),
+
+    #[error("checksum mismatch for tensor {name:?}: expected {expected:x?}, got {actual:x?}")]
+    ChecksumMismatch {
+        name: Vec<u8>,
+        expected: [u8; 32],
+        actual: [u8; 32],
+    },
+}
+
+/// A streaming hash function fed each tensor's raw bytes as they're loaded,
+/// used by [`load_weights_verified`] to detect truncated or corrupted
+/// models. Implementations are expected to support being reused across
+/// multiple tensors via [`Digest::finalize_reset`].
+pub trait Digest {
+    /// Feed more bytes into the running hash.
+    fn update(&mut self, data: &[u8]);
+    /// Finalize the hash accumulated so far and reset internal state so the
+    /// same `Digest` can be reused for the next tensor.
+    fn finalize_reset(&mut self) -> [u8; 32];
 }
 
 #[derive(Debug, Clone)]
@@ -68,8 +87,56 @@ pub trait LoadHandler<E: Error, R: BufRead + Seek> {
     /// Called when the hyperparameters need to be read.
     /// You must read the hyperparameters for your model here.
     fn read_hyperparameters(&mut self, reader: &mut R) -> Result<PartialHyperparameters, E>;
-    /// Called when a new tensor is found.
+    /// Called when a new tensor is found by [`load_weights`] (and, through
+    /// it, [`load_model_from_reader`] and [`load_adapter_from_reader`]).
+    /// Only the tensor's [`TensorInfo`] is passed; its weight bytes start at
+    /// `info.start_offset`, and after this returns [`load_weights`] seeks
+    /// past them to `start_offset + calc_size()`.
+    ///
+    /// Not called by [`load_weights_verified`]: that path reads every
+    /// tensor's bytes itself to feed them through the digest. Use
+    /// [`LoadHandler::tensor_digest`] instead to observe tensors loaded
+    /// that way.
     fn tensor_buffer(&mut self, info: TensorInfo) -> Result<(), E>;
+    /// Called when the LoRA adapter hyperparameters (rank and alpha) are
+    /// read from a [`ContainerType::GGLA`] file. The scale applied to a
+    /// tensor is `alpha / r`. Defaults to doing nothing, for handlers that
+    /// don't support loading adapters.
+    fn lora_parameters(&mut self, r: i32, alpha: i32) -> Result<(), E> {
+        let _ = (r, alpha);
+        Ok(())
+    }
+    /// Called when a new tensor is found while loading from a memory-mapped
+    /// file via [`load_model_from_mmap`]. `data` is a zero-copy subslice of
+    /// the mapping spanning exactly `info.calc_size()` bytes at
+    /// `info.start_offset`. Defaults to doing nothing, for handlers that
+    /// don't support mmap loading.
+    fn tensor_mapped(&mut self, info: TensorInfo, data: &[u8]) -> Result<(), E> {
+        let _ = (info, data);
+        Ok(())
+    }
+    /// Called with the digest of a tensor's raw bytes by
+    /// [`load_weights_verified`]. Defaults to doing nothing.
+    fn tensor_digest(&mut self, info: TensorInfo, digest: &[u8; 32]) -> Result<(), E> {
+        let _ = (info, digest);
+        Ok(())
+    }
+    /// Called once, after all tensors have been loaded by
+    /// [`load_weights_verified`], with a rolling hash over every per-tensor
+    /// digest. Defaults to doing nothing.
+    fn model_digest(&mut self, digest: &[u8; 32]) -> Result<(), E> {
+        let _ = digest;
+        Ok(())
+    }
+    /// Returns the expected digest for a tensor by name, if the caller wants
+    /// [`load_weights_verified`] to verify it and fail fast with
+    /// [`LoadError::ChecksumMismatch`] on a mismatch. Defaults to `None`,
+    /// meaning the digest is still reported via
+    /// [`LoadHandler::tensor_digest`] but never checked.
+    fn expected_tensor_digest(&mut self, name: &[u8]) -> Option<[u8; 32]> {
+        let _ = name;
+        None
+    }
 }
 
 #[test]
@@ -102,6 +169,7 @@ pub fn load_model_from_reader<E: Error, R: BufRead + Seek>(
             };
         }
         ContainerType::GGML => {}
+        ContainerType::GGLA => unreachable!("GGLA adapters are loaded via load_adapter_from_reader"),
     }
 
     // Load hyper params
@@ -120,6 +188,7 @@ pub fn load_model_from_reader<E: Error, R: BufRead + Seek>(
                 // Legacy model, set empty score
                 0.
             }
+            ContainerType::GGLA => unreachable!("GGLA adapters are loaded via load_adapter_from_reader"),
         };
         handler
             .vocabulary_token(i, token, token_score)
@@ -130,9 +199,102 @@ pub fn load_model_from_reader<E: Error, R: BufRead + Seek>(
     match container_type {
         ContainerType::GGMF | ContainerType::GGML => load_weights(reader, handler, false),
         ContainerType::GGJT => load_weights(reader, handler, true),
+        ContainerType::GGLA => unreachable!("GGLA adapters are loaded via load_adapter_from_reader"),
     }
 }
 
+/// Loads a LoRA adapter, as produced by llama.cpp's `convert-lora-to-ggml.py`.
+///
+/// Unlike [`load_model_from_reader`], a GGLA file has no vocabulary section:
+/// the header is immediately followed by the tensor data, which comes in
+/// `.loraA`/`.loraB` pairs. The tensor data is unaligned, just like
+/// [`ContainerType::GGML`], so it reuses the same [`load_weights`] loop.
+pub fn load_adapter_from_reader<E: Error, R: BufRead + Seek>(
+    reader: &mut R,
+    handler: &mut impl LoadHandler<E, R>,
+) -> Result<(), LoadError<E>> {
+    // Verify magic
+    let magic = read_u32(reader)?;
+    if magic != ggml::FILE_MAGIC_GGLA {
+        return Err(LoadError::InvalidMagic(magic));
+    }
+    handler
+        .container_type(ContainerType::GGLA)
+        .map_err(LoadError::ImplementationError)?;
+
+    // Load format version
+    let _version: u32 = match read_u32(reader)? {
+        ggml::FORMAT_VERSION => ggml::FORMAT_VERSION,
+        version => return Err(LoadError::InvalidFormatVersion(ContainerType::GGLA, version)),
+    };
+
+    // Load LoRA hyperparameters (rank and alpha)
+    let r = read_i32(reader)?;
+    let alpha = read_i32(reader)?;
+    handler
+        .lora_parameters(r, alpha)
+        .map_err(LoadError::ImplementationError)?;
+
+    // No vocabulary section; go straight to the tensor data.
+    load_weights(reader, handler, false)
+}
+
+/// Reads one tensor header (dims, name, type) and computes its aligned
+/// `start_offset`, without reading or skipping the tensor's weight bytes.
+fn read_tensor_header<E: Error, R: BufRead + Seek>(
+    reader: &mut R,
+    align: bool,
+) -> Result<TensorInfo, LoadError<E>> {
+    let n_dims: usize = read_i32(reader)?.try_into()?;
+    let name_len = read_i32(reader)?;
+    let ftype = read_i32(reader)?;
+    let ftype =
+        ggml::Type::try_from(ftype).map_err(|_| LoadError::UnsupportedElementType(ftype))?;
+
+    let mut n_elements: usize = 1;
+    let mut dims = [1usize, 1];
+    let ne_len = dims.len();
+    if !(n_dims <= ne_len) {
+        return Err(LoadError::InvariantBroken(format!("{n_dims} <= {ne_len}")));
+    }
+
+    #[allow(clippy::needless_range_loop)]
+    for i in 0..n_dims {
+        let dim: usize = read_i32(reader)?.try_into()?;
+        dims[i] = dim;
+        n_elements *= dim;
+    }
+
+    // load tensor name
+    let name = read_bytes_with_len(reader, name_len.try_into()?)?;
+
+    // sanity check: every quantized row must hold a whole number of blocks
+    let blck_size = ggml::blck_size(ftype);
+    if blck_size > 1 && dims[0] % blck_size != 0 {
+        return Err(LoadError::InvariantBroken(format!(
+            "{dims:?}[0] % {blck_size} == 0"
+        )));
+    }
+
+    let offset_curr = reader.stream_position()?;
+    let offset_aligned: u64 = if align {
+        (offset_curr + 31) & !31
+    } else {
+        offset_curr
+    };
+
+    let tensor_info = TensorInfo {
+        name,
+        dims,
+        n_dims,
+        n_elements,
+        element_type: ftype,
+        start_offset: offset_aligned,
+    };
+
+    Ok(tensor_info)
+}
+
 /// # Params
 ///
 /// `align`
@@ -143,61 +305,456 @@ fn load_weights<E: Error, R: BufRead + Seek>(
     align: bool,
 ) -> Result<(), LoadError<E>> {
     while has_data_left(reader)? {
-        // load tensor header
-        let n_dims: usize = read_i32(reader)?.try_into()?;
-        let name_len = read_i32(reader)?;
-        let ftype = read_i32(reader)?;
-        let ftype =
-            ggml::Type::try_from(ftype).map_err(|_| LoadError::UnsupportedElementType(ftype))?;
-
-        let mut n_elements: usize = 1;
-        let mut dims = [1usize, 1];
-        let ne_len = dims.len();
-        if !(n_dims <= ne_len) {
-            return Err(LoadError::InvariantBroken(format!("{n_dims} <= {ne_len}")));
+        let tensor_info = read_tensor_header(reader, align)?;
+        let offset_aligned = tensor_info.start_offset;
+        let n_bytes = tensor_info.calc_size();
+
+        handler
+            .tensor_buffer(tensor_info)
+            .map_err(LoadError::ImplementationError)?;
+        reader.seek(SeekFrom::Start(offset_aligned + n_bytes as u64))?;
+    }
+
+    Ok(())
+}
+
+/// Like [`load_weights`], but reads every tensor's raw bytes through
+/// `digest` instead of skipping past them, reporting each tensor's hash via
+/// [`LoadHandler::tensor_digest`] and a final rolling hash over all of them
+/// via [`LoadHandler::model_digest`]. This is what makes verification
+/// possible on the GGJT skip path, which otherwise never reads tensor
+/// bytes at all.
+///
+/// Unlike [`load_weights`], this does not call [`LoadHandler::tensor_buffer`]:
+/// by the time a tensor's digest is ready to report, its bytes have already
+/// been consumed from `reader` and there's nothing left for a
+/// `tensor_buffer` implementation to read. Tensors are surfaced to the
+/// handler via [`LoadHandler::tensor_digest`] only.
+///
+/// If [`LoadHandler::expected_tensor_digest`] returns `Some` for a tensor
+/// and it doesn't match, loading fails fast with
+/// [`LoadError::ChecksumMismatch`].
+pub fn load_weights_verified<E: Error, R: BufRead + Seek>(
+    reader: &mut R,
+    handler: &mut impl LoadHandler<E, R>,
+    align: bool,
+    digest: &mut dyn Digest,
+) -> Result<(), LoadError<E>> {
+    let mut rolling = Vec::new();
+
+    while has_data_left(reader)? {
+        let tensor_info = read_tensor_header(reader, align)?;
+        let n_bytes = tensor_info.calc_size();
+
+        let mut bytes = vec![0u8; n_bytes];
+        reader.read_exact(&mut bytes)?;
+        digest.update(&bytes);
+        let tensor_digest = digest.finalize_reset();
+        rolling.extend_from_slice(&tensor_digest);
+
+        if let Some(expected) = handler.expected_tensor_digest(&tensor_info.name) {
+            if expected != tensor_digest {
+                return Err(LoadError::ChecksumMismatch {
+                    name: tensor_info.name,
+                    expected,
+                    actual: tensor_digest,
+                });
+            }
         }
 
-        #[allow(clippy::needless_range_loop)]
-        for i in 0..n_dims {
-            let dim: usize = read_i32(reader)?.try_into()?;
-            dims[i] = dim;
-            n_elements *= dim;
+        handler
+            .tensor_digest(tensor_info, &tensor_digest)
+            .map_err(LoadError::ImplementationError)?;
+    }
+
+    digest.update(&rolling);
+    let model_digest = digest.finalize_reset();
+    handler
+        .model_digest(&model_digest)
+        .map_err(LoadError::ImplementationError)?;
+
+    Ok(())
+}
+
+/// Like [`load_model_from_reader`], but verifies tensor data as it loads
+/// instead of handing it straight to the handler: walks the magic, format
+/// version, hyperparameters and vocabulary exactly the same way, then
+/// delegates the tensor data to [`load_weights_verified`] rather than
+/// [`load_weights`]. Use this instead of hand-parsing the header yourself
+/// just to get to [`load_weights_verified`].
+pub fn load_model_from_reader_verified<E: Error, R: BufRead + Seek>(
+    reader: &mut R,
+    handler: &mut impl LoadHandler<E, R>,
+    digest: &mut dyn Digest,
+) -> Result<(), LoadError<E>> {
+    // Verify magic
+    let container_type: ContainerType = match read_u32(reader)? {
+        ggml::FILE_MAGIC_GGMF => ContainerType::GGMF,
+        ggml::FILE_MAGIC_GGJT => ContainerType::GGJT,
+        ggml::FILE_MAGIC_UNVERSIONED => ContainerType::GGML,
+        magic => return Err(LoadError::InvalidMagic(magic)),
+    };
+    handler
+        .container_type(container_type)
+        .map_err(LoadError::ImplementationError)?;
+
+    // Load format version
+    match container_type {
+        ContainerType::GGMF | ContainerType::GGJT => {
+            let _version: u32 = match read_u32(reader)? {
+                ggml::FORMAT_VERSION => ggml::FORMAT_VERSION,
+                version => return Err(LoadError::InvalidFormatVersion(container_type, version)),
+            };
         }
+        ContainerType::GGML => {}
+        ContainerType::GGLA => unreachable!("GGLA adapters are loaded via load_adapter_from_reader"),
+    }
 
-        // load tensor name
-        let name = read_bytes_with_len(reader, name_len.try_into()?)?;
+    // Load hyper params
+    let hparams = handler
+        .read_hyperparameters(reader)
+        .map_err(LoadError::ImplementationError)?;
+    let n_vocab = hparams.n_vocab;
 
-        // sanity check
-        match ftype {
-            ElementType::Q4_0 | ElementType::Q4_1 => {
-                if !(dims[0] % 64 == 0) {
-                    return Err(LoadError::InvariantBroken(format!("{dims:?}[0] % 64 == 0")));
-                }
+    // Load vocabulary
+    for i in 0..n_vocab {
+        let len = read_u32(reader)?.try_into()?;
+        let token = read_bytes_with_len(reader, len)?;
+        let token_score = match container_type {
+            ContainerType::GGMF | ContainerType::GGJT => read_f32(reader)?,
+            ContainerType::GGML => {
+                // Legacy model, set empty score
+                0.
             }
-            _ => {}
+            ContainerType::GGLA => unreachable!("GGLA adapters are loaded via load_adapter_from_reader"),
+        };
+        handler
+            .vocabulary_token(i, token, token_score)
+            .map_err(LoadError::ImplementationError)?;
+    }
+
+    // Load and verify tensor data
+    match container_type {
+        ContainerType::GGMF | ContainerType::GGML => {
+            load_weights_verified(reader, handler, false, digest)
         }
+        ContainerType::GGJT => load_weights_verified(reader, handler, true, digest),
+        ContainerType::GGLA => unreachable!("GGLA adapters are loaded via load_adapter_from_reader"),
+    }
+}
 
-        // load tensor weights
-        let offset_curr = reader.stream_position()?;
-        let offset_aligned: u64 = if align {
-            (offset_curr + 31) & !31
-        } else {
-            offset_curr
+/// Loads a [`ContainerType::GGJT`] model from a memory-mapped byte slice,
+/// handing back zero-copy subslices of `data` via
+/// [`LoadHandler::tensor_mapped`] instead of copying tensor weights into a
+/// caller-provided buffer. This is the whole point of GGJT's 32-byte
+/// alignment: tensor data can be referenced directly out of the mapping,
+/// shared through the page cache, with no allocation or `read_exact`.
+///
+/// Other container types don't support this path, since their tensor data
+/// isn't aligned.
+pub fn load_model_from_mmap<E: Error>(
+    data: &[u8],
+    handler: &mut impl LoadHandler<E, Cursor<&[u8]>>,
+) -> Result<(), LoadError<E>> {
+    let mut reader = Cursor::new(data);
+
+    let magic = read_u32(&mut reader)?;
+    let container_type = match magic {
+        ggml::FILE_MAGIC_GGJT => ContainerType::GGJT,
+        ggml::FILE_MAGIC_GGMF => ContainerType::GGMF,
+        ggml::FILE_MAGIC_UNVERSIONED => ContainerType::GGML,
+        _ => return Err(LoadError::InvalidMagic(magic)),
+    };
+    if container_type != ContainerType::GGJT {
+        return Err(LoadError::InvariantBroken(format!(
+            "{container_type:?} does not support mmap loading; only GGJT is 32-byte aligned"
+        )));
+    }
+    handler
+        .container_type(container_type)
+        .map_err(LoadError::ImplementationError)?;
+
+    let _version: u32 = match read_u32(&mut reader)? {
+        ggml::FORMAT_VERSION => ggml::FORMAT_VERSION,
+        version => return Err(LoadError::InvalidFormatVersion(container_type, version)),
+    };
+
+    let hparams = handler
+        .read_hyperparameters(&mut reader)
+        .map_err(LoadError::ImplementationError)?;
+
+    for i in 0..hparams.n_vocab {
+        let len = read_u32(&mut reader)?.try_into()?;
+        let token = read_bytes_with_len(&mut reader, len)?;
+        let token_score = read_f32(&mut reader)?;
+        handler
+            .vocabulary_token(i, token, token_score)
+            .map_err(LoadError::ImplementationError)?;
+    }
+
+    while has_data_left(&mut reader)? {
+        let tensor_info = read_tensor_header(&mut reader, true)?;
+        let start = tensor_info.start_offset as usize;
+        let n_bytes = tensor_info.calc_size();
+        let end = start
+            .checked_add(n_bytes)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| {
+                LoadError::InvariantBroken(format!("tensor at {start}..+{n_bytes} out of bounds"))
+            })?;
+
+        handler
+            .tensor_mapped(tensor_info, &data[start..end])
+            .map_err(LoadError::ImplementationError)?;
+        reader.seek(SeekFrom::Start((start + n_bytes) as u64))?;
+    }
+
+    Ok(())
+}
+
+/// A `Read`-only adapter that implements `BufRead + Seek` by tracking a
+/// virtual position and draining bytes to satisfy forward seeks.
+///
+/// [`load_weights`] with `align = false` (used for GGML/GGMF) never seeks
+/// backward: it only seeks past tensors the handler chose not to load. That
+/// makes it safe to run over a compressed, non-seekable stream as long as
+/// backward seeks are rejected, which is exactly what this adapter does.
+pub struct ForwardSeekReader<R: Read> {
+    inner: io::BufReader<R>,
+    position: u64,
+}
+
+impl<R: Read> ForwardSeekReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner: io::BufReader::new(inner),
+            position: 0,
+        }
+    }
+}
+
+impl<R: Read> Read for ForwardSeekReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl<R: Read> BufRead for ForwardSeekReader<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+        self.position += amt as u64;
+    }
+}
+
+impl<R: Read> Seek for ForwardSeekReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(n) => n,
+            SeekFrom::Current(delta) => (self.position as i64 + delta) as u64,
+            SeekFrom::End(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "cannot seek from the end of a compressed, non-seekable stream",
+                ))
+            }
         };
+        if target < self.position {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "cannot seek backward in a compressed, non-seekable stream",
+            ));
+        }
 
-        let tensor_info = TensorInfo {
-            name,
-            dims,
-            n_dims,
-            n_elements,
-            element_type: ftype,
-            start_offset: offset_aligned,
+        let mut remaining = target - self.position;
+        let mut scratch = [0u8; 4096];
+        while remaining > 0 {
+            let chunk = remaining.min(scratch.len() as u64) as usize;
+            self.inner.read_exact(&mut scratch[..chunk])?;
+            self.position += chunk as u64;
+            remaining -= chunk as u64;
+        }
+        Ok(self.position)
+    }
+
+    fn stream_position(&mut self) -> io::Result<u64> {
+        Ok(self.position)
+    }
+}
+
+/// Loads a [`ContainerType::GGMF`] or [`ContainerType::GGML`] model from an
+/// arbitrary decompressing `Read`, such as a zstd or lzma decoder wrapped
+/// around a file on disk. GGJT is rejected with
+/// [`LoadError::InvariantBroken`]; only the unaligned GGMF and GGML
+/// containers are accepted.
+pub fn load_model_from_compressed_reader<E: Error, D: Read>(
+    decoder: D,
+    handler: &mut impl LoadHandler<E, ForwardSeekReader<D>>,
+) -> Result<(), LoadError<E>> {
+    let mut reader = ForwardSeekReader::new(decoder);
+
+    // Verify magic
+    let container_type: ContainerType = match read_u32(&mut reader)? {
+        ggml::FILE_MAGIC_GGMF => ContainerType::GGMF,
+        ggml::FILE_MAGIC_UNVERSIONED => ContainerType::GGML,
+        ggml::FILE_MAGIC_GGJT => {
+            return Err(LoadError::InvariantBroken(
+                "GGJT requires seekable storage for its 32-byte tensor alignment; \
+                 decompress it to a file before loading"
+                    .to_string(),
+            ))
+        }
+        magic => return Err(LoadError::InvalidMagic(magic)),
+    };
+    handler
+        .container_type(container_type)
+        .map_err(LoadError::ImplementationError)?;
+
+    // Load format version
+    if container_type == ContainerType::GGMF {
+        let _version: u32 = match read_u32(&mut reader)? {
+            ggml::FORMAT_VERSION => ggml::FORMAT_VERSION,
+            version => return Err(LoadError::InvalidFormatVersion(container_type, version)),
+        };
+    }
+
+    // Load hyper params
+    let hparams = handler
+        .read_hyperparameters(&mut reader)
+        .map_err(LoadError::ImplementationError)?;
+
+    // Load vocabulary
+    for i in 0..hparams.n_vocab {
+        let len = read_u32(&mut reader)?.try_into()?;
+        let token = read_bytes_with_len(&mut reader, len)?;
+        let token_score = match container_type {
+            ContainerType::GGMF => read_f32(&mut reader)?,
+            // Legacy model, set empty score
+            _ => 0.,
         };
-        let n_bytes = tensor_info.calc_size();
         handler
-            .tensor_buffer(tensor_info)
+            .vocabulary_token(i, token, token_score)
+            .map_err(LoadError::ImplementationError)?;
+    }
+
+    load_weights(&mut reader, handler, false)
+}
+
+/// Convenience wrapper around [`load_model_from_compressed_reader`] for
+/// zstd-compressed models.
+#[cfg(feature = "compress-zstd")]
+pub fn load_model_from_zstd_reader<'a, E: Error, R: Read>(
+    reader: R,
+    handler: &mut impl LoadHandler<E, ForwardSeekReader<zstd::Decoder<'a, io::BufReader<R>>>>,
+) -> Result<(), LoadError<E>> {
+    let decoder = zstd::Decoder::new(reader)?;
+    load_model_from_compressed_reader(decoder, handler)
+}
+
+/// Convenience wrapper around [`load_model_from_compressed_reader`] for
+/// lzma/xz-compressed models.
+#[cfg(feature = "compress-lzma")]
+pub fn load_model_from_lzma_reader<E: Error, R: Read>(
+    reader: R,
+    handler: &mut impl LoadHandler<E, ForwardSeekReader<xz2::read::XzDecoder<R>>>,
+) -> Result<(), LoadError<E>> {
+    let decoder = xz2::read::XzDecoder::new(reader);
+    load_model_from_compressed_reader(decoder, handler)
+}
+
+/// Magic for the GGMC container written by `llama-rs`'s
+/// `llama_model_quantize(.., compress: true, ..)`. Its header, hyperparameter
+/// and vocabulary layout are identical to GGMF's, but every tensor's payload
+/// is independently zstd-compressed and prefixed with `compressed_len: u32`
+/// then `uncompressed_len: u32` (both little-endian) instead of being stored
+/// raw, so it can't be read by [`load_weights`]'s generic skip-by-size loop.
+const FILE_MAGIC_GGMC: u32 = 0x67_67_6d_63; // "ggmc"
+
+/// Loads a GGMC model, decompressing each tensor's payload as it's reached
+/// instead of buffering the whole file. GGMC's header, hyperparameters and
+/// vocabulary are laid out exactly like GGMF's (real per-token scores,
+/// unaligned tensor data), so this reuses the same steps as
+/// [`load_model_from_reader`]'s GGMF path for everything up to the tensor
+/// data, then reads each tensor's `compressed_len`/`uncompressed_len` prefix,
+/// decompresses its payload with zstd, and hands the decompressed bytes to
+/// the handler via [`LoadHandler::tensor_mapped`].
+///
+/// This doesn't call [`LoadHandler::container_type`]: GGMC isn't one of
+/// [`ContainerType`]'s variants, and reporting it as GGMF or GGML would be
+/// misleading about the on-disk layout. Callers of this function already
+/// know they're loading GGMC, since that's the only thing it reads.
+#[cfg(feature = "compress-zstd")]
+pub fn load_model_from_ggmc_reader<E: Error, R: BufRead + Seek>(
+    reader: &mut R,
+    handler: &mut impl LoadHandler<E, R>,
+) -> Result<(), LoadError<E>> {
+    // Verify magic
+    let magic = read_u32(reader)?;
+    if magic != FILE_MAGIC_GGMC {
+        return Err(LoadError::InvalidMagic(magic));
+    }
+
+    // Load format version
+    let _version: u32 = match read_u32(reader)? {
+        ggml::FORMAT_VERSION => ggml::FORMAT_VERSION,
+        version => {
+            return Err(LoadError::InvariantBroken(format!(
+                "unsupported GGMC format version: {version}"
+            )))
+        }
+    };
+
+    // Load hyper params
+    let hparams = handler
+        .read_hyperparameters(reader)
+        .map_err(LoadError::ImplementationError)?;
+
+    // Load vocabulary
+    for i in 0..hparams.n_vocab {
+        let len = read_u32(reader)?.try_into()?;
+        let token = read_bytes_with_len(reader, len)?;
+        let token_score = read_f32(reader)?;
+        handler
+            .vocabulary_token(i, token, token_score)
+            .map_err(LoadError::ImplementationError)?;
+    }
+
+    // Load tensor data: each header is immediately followed by
+    // `compressed_len: u32`, `uncompressed_len: u32`, then exactly
+    // `compressed_len` bytes of independently zstd-compressed payload.
+    while has_data_left(reader)? {
+        let tensor_info = read_tensor_header(reader, false)?;
+        let uncompressed_len = tensor_info.calc_size();
+
+        let compressed_len: usize = read_u32(reader)?.try_into()?;
+        let stored_uncompressed_len: usize = read_u32(reader)?.try_into()?;
+        if stored_uncompressed_len != uncompressed_len {
+            return Err(LoadError::InvariantBroken(format!(
+                "tensor {:?}: uncompressed length {stored_uncompressed_len} in file doesn't \
+                 match computed size {uncompressed_len}",
+                String::from_utf8_lossy(&tensor_info.name)
+            )));
+        }
+
+        let mut compressed = vec![0u8; compressed_len];
+        reader.read_exact(&mut compressed)?;
+        let data = zstd::decode_all(&compressed[..])?;
+        if data.len() != uncompressed_len {
+            return Err(LoadError::InvariantBroken(format!(
+                "tensor {:?}: decompressed to {} bytes, expected {uncompressed_len}",
+                String::from_utf8_lossy(&tensor_info.name),
+                data.len()
+            )));
+        }
+
+        handler
+            .tensor_mapped(tensor_info, &data)
             .map_err(LoadError::ImplementationError)?;
-        reader.seek(SeekFrom::Start(offset_aligned + n_bytes as u64))?;
     }
 
     Ok(())
@@ -1,31 +1,445 @@
 use crate::ggml::{
-    quantize_q4_0, quantize_q4_1, FILE_MAGIC, FILE_MAGIC_UNVERSIONED, FORMAT_VERSION, TYPE_Q4_0,
-    TYPE_Q4_1,
+    dequantize_row_q4_0, dequantize_row_q4_1, quantize_q4_0, quantize_q4_1, FILE_MAGIC,
+    FILE_MAGIC_UNVERSIONED, FORMAT_VERSION, TYPE_Q4_0, TYPE_Q4_1,
 };
 use crate::{Hyperparameters, LoadError, Vocabulary};
 use half::f16;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Read, Write};
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
 use std::path::Path;
+use std::sync::Mutex;
 use thiserror::Error;
 
+/// Magic identifying the optional checksum trailer appended by
+/// [`llama_model_quantize`] when `checksum` is set, and expected by
+/// [`llama_model_verify`]. Files without it load exactly as before. Files
+/// with it are rejected by `ggml-format`'s loaders: their tensor loops run
+/// until end of file, so they parse the trailer as one more tensor header
+/// and fail.
+const CHECKSUM_TRAILER_MAGIC: u32 = 0x53_4b_43_51; // "QCKS" read little-endian
+
+/// Magic written in place of [`FILE_MAGIC`] when `llama_model_quantize` is
+/// called with `compress: true`. Every tensor's payload is then prefixed
+/// with its compressed and uncompressed lengths and zstd-compressed
+/// independently of the others, so [`llama_model_verify`] and
+/// `ggml-format`'s `load_model_from_ggmc_reader` can both decompress and
+/// check/load tensors one at a time instead of buffering the whole model.
+///
+/// `ggml-format`'s `load_model_from_reader` still rejects this magic with
+/// `InvalidMagic`: it isn't one of the `ggml` crate's container types, and
+/// `load_model_from_reader`'s generic skip-by-size tensor loop can't skip a
+/// compressed tensor (its on-disk length isn't the logical tensor size).
+/// Load a GGMC file via `ggml-format`'s `load_model_from_ggmc_reader`
+/// instead. Plain [`FILE_MAGIC`] files are unaffected and keep loading
+/// exactly as before.
+const FILE_MAGIC_GGMC: u32 = 0x67_67_6d_63; // "ggmc"
+
+/// Recorded per-tensor integrity data, written to the trailer by
+/// [`llama_model_quantize`] and checked by [`llama_model_verify`].
+struct TensorChecksum {
+    name: String,
+    payload_len: u64,
+    crc32: u32,
+    max_abs_err: f32,
+    mean_abs_err: f32,
+}
+
+/// The target precision for a single tensor, as decided by a
+/// [`QuantizePolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuantizeTarget {
+    /// Leave the tensor exactly as stored in the source file.
+    Unquantized,
+    /// Quantize to 4-bit, method 0 ([`TYPE_Q4_0`]).
+    Q4_0,
+    /// Quantize to 4-bit, method 1 ([`TYPE_Q4_1`]).
+    Q4_1,
+}
+
+impl QuantizeTarget {
+    fn otype(self) -> Option<u8> {
+        match self {
+            QuantizeTarget::Unquantized => None,
+            QuantizeTarget::Q4_0 => Some(TYPE_Q4_0),
+            QuantizeTarget::Q4_1 => Some(TYPE_Q4_1),
+        }
+    }
+}
+
+/// Matches tensors whose name contains `name_contains`, assigning them
+/// `target`. Rules are checked in the order they appear in
+/// [`QuantizePolicy::rules`]; the first match wins. Callers needing
+/// per-layer control can match on the layer index embedded in the name
+/// (e.g. a rule with `name_contains: "layers.0.".to_string()`).
+pub struct QuantizeRule {
+    pub name_contains: String,
+    pub target: QuantizeTarget,
+}
+
+/// Decides which [`QuantizeTarget`] each tensor is written as, replacing
+/// the old hardcoded "2D tensor whose name contains `weight`" rule plus a
+/// single model-wide `itype`. Only 2D tensors are ever quantized (1D
+/// tensors such as norms are always left as-is, matching llama.cpp); for
+/// those, `rules` is checked in order and `default` applies to anything
+/// left unmatched.
+pub struct QuantizePolicy {
+    pub rules: Vec<QuantizeRule>,
+    pub default: QuantizeTarget,
+}
+
+impl QuantizePolicy {
+    /// Reproduces today's default behavior: every 2D tensor whose name
+    /// contains `weight` is quantized to `target`, everything else is left
+    /// unquantized.
+    pub fn uniform(target: QuantizeTarget) -> Self {
+        Self {
+            rules: vec![QuantizeRule {
+                name_contains: "weight".to_string(),
+                target,
+            }],
+            default: QuantizeTarget::Unquantized,
+        }
+    }
+
+    fn target_for(&self, name: &str, n_dims: i32) -> QuantizeTarget {
+        if n_dims != 2 {
+            return QuantizeTarget::Unquantized;
+        }
+        self.rules
+            .iter()
+            .find(|rule| name.contains(&rule.name_contains))
+            .map(|rule| rule.target)
+            .unwrap_or(self.default)
+    }
+
+    /// The quantized type recorded in the file header, which (for backwards
+    /// compatibility with tools that read it) still records a single
+    /// representative type even though tensors may now be mixed-precision;
+    /// the authoritative per-tensor type is always the one written
+    /// alongside each tensor's own header.
+    fn header_ftype(&self) -> u8 {
+        self.rules
+            .iter()
+            .map(|rule| rule.target)
+            .chain(std::iter::once(self.default))
+            .find_map(QuantizeTarget::otype)
+            .unwrap_or(TYPE_Q4_1)
+    }
+}
+
+/// A tensor read off disk, ready to be (optionally) quantized. Holds the raw
+/// header fields plus either `data_f32` (for tensors we're about to
+/// quantize) or `data_u8` (for tensors passed through unchanged). Tensors
+/// are streamed one at a time off `finp` into a bounded worker pool (see
+/// `llama_model_quantize`'s weight-loading loop), so at most a handful of
+/// these are ever resident at once rather than the whole model.
+struct TensorJob {
+    index: usize,
+    name: String,
+    n_dims: i32,
+    ne: [i32; 2],
+    nelements: i32,
+    src_ftype: usize,
+    target: QuantizeTarget,
+    data_f32: Vec<f32>,
+    data_u8: Vec<u8>,
+}
+
+/// The (possibly quantized) output for one [`TensorJob`], in the same order
+/// as the job it was produced from. Carries everything the writer needs to
+/// serialize the tensor so the originating [`TensorJob`] (and its `data_f32`
+/// / `data_u8` buffers) can be dropped the moment quantization finishes,
+/// instead of staying resident until the whole model has been processed.
+struct TensorResult {
+    index: usize,
+    name: String,
+    n_dims: i32,
+    ne: [i32; 2],
+    nelements: i32,
+    src_ftype: usize,
+    ftype: usize,
+    /// Serialized tensor payload, ready to be written to `fout` as-is.
+    payload: Vec<u8>,
+    hist: Vec<i64>,
+    /// Round-trip dequantization error versus the original f32 values.
+    /// `0.0` for tensors that weren't quantized.
+    max_abs_err: f32,
+    mean_abs_err: f32,
+}
+
+fn quantize_job(job: TensorJob, qk: u8) -> Result<TensorResult, bool> {
+    let TensorJob {
+        index,
+        name,
+        n_dims,
+        ne,
+        nelements,
+        src_ftype,
+        target,
+        data_f32,
+        data_u8,
+    } = job;
+
+    let Some(otype) = target.otype() else {
+        return Ok(TensorResult {
+            index,
+            name,
+            n_dims,
+            ne,
+            nelements,
+            src_ftype,
+            ftype: src_ftype,
+            payload: data_u8,
+            hist: vec![0; 16],
+            max_abs_err: 0.0,
+            mean_abs_err: 0.0,
+        });
+    };
+
+    let mut work = vec![0.0f32; nelements as usize];
+    let mut data_f32_scratch = data_f32.clone();
+    let mut hist_cur = vec![0; 16];
+
+    let curr_size = match otype {
+        TYPE_Q4_0 => quantize_q4_0(
+            &mut data_f32_scratch,
+            &mut work,
+            nelements,
+            ne[0],
+            qk as i32,
+            &mut hist_cur,
+        ),
+        TYPE_Q4_1 => quantize_q4_1(
+            &mut data_f32_scratch,
+            &mut work,
+            nelements,
+            ne[0],
+            qk as i32,
+            &mut hist_cur,
+        ),
+        _ => return Err(false),
+    };
+
+    let mut payload = Vec::with_capacity(curr_size);
+    for word in &work[..curr_size / 4] {
+        payload.extend_from_slice(&word.to_le_bytes());
+    }
+
+    // Round-trip: dequantize what we just wrote and compare it against the
+    // original values, to catch a buggy quantization pass.
+    let mut dequantized = vec![0.0f32; nelements as usize];
+    match otype {
+        TYPE_Q4_0 => dequantize_row_q4_0(&work, &mut dequantized, nelements),
+        TYPE_Q4_1 => dequantize_row_q4_1(&work, &mut dequantized, nelements),
+        _ => return Err(false),
+    }
+    let mut max_abs_err = 0.0f32;
+    let mut sum_abs_err = 0.0f32;
+    for (orig, deq) in data_f32.iter().zip(dequantized.iter()) {
+        let err = (orig - deq).abs();
+        max_abs_err = max_abs_err.max(err);
+        sum_abs_err += err;
+    }
+    let mean_abs_err = sum_abs_err / nelements as f32;
+
+    Ok(TensorResult {
+        index,
+        name,
+        n_dims,
+        ne,
+        nelements,
+        src_ftype,
+        ftype: otype as usize,
+        payload,
+        hist: hist_cur,
+        max_abs_err,
+        mean_abs_err,
+    })
+}
+
 const FTYPE_STR: [&str; 4] = ["f32", "f16", "q4_0", "q4_1"];
 
+/// Callbacks invoked by [`llama_model_quantize`] as it works, so a caller
+/// embedding this in a GUI or server can drive its own progress UI instead
+/// of the default stderr output. Every method has a no-op default, so
+/// implementors only need to override what they care about.
+#[allow(unused_variables)]
+pub trait QuantizeReporter {
+    /// Called once per tensor, in file order, before it's written out.
+    fn on_tensor(&mut self, name: &str, dims: &[i32], src_ftype: usize, dst_ftype: usize) {}
+
+    /// Called after each tensor is written, with the running byte totals
+    /// measured against the uncompressed size of the source file.
+    fn on_progress(&mut self, bytes_done: u64, bytes_total: u64) {}
+
+    /// Called once at the end with the overall size comparison, the
+    /// combined quantization histogram (16 bins) across all tensors, and
+    /// the uncompressed payload bytes per output type (in [`FTYPE_STR`]
+    /// order).
+    fn on_finished(&mut self, orig_size: u64, new_size: u64, histogram: &[i64], size_by_ftype: &[usize]) {}
+}
+
+/// Prints [`llama_model_quantize`]'s progress to stderr: one line per tensor
+/// (name, dims, source and target type) and, at the end, the model and
+/// quantized sizes, the combined histogram and the per-type sizes. The
+/// hyperparameters and per-tensor sizes and histograms are not printed.
+pub struct StderrReporter;
+
+impl QuantizeReporter for StderrReporter {
+    fn on_tensor(&mut self, name: &str, dims: &[i32], src_ftype: usize, dst_ftype: usize) {
+        eprintln!(
+            "{:>48} - {:?}, type = {:>6} -> {:>6}",
+            format!("'{}'", name),
+            dims,
+            FTYPE_STR[src_ftype],
+            FTYPE_STR[dst_ftype]
+        );
+    }
+
+    fn on_finished(&mut self, orig_size: u64, new_size: u64, histogram: &[i64], size_by_ftype: &[usize]) {
+        eprintln!("model size: {:>8.2}", orig_size as f32 / 1024.0 / 1024.0);
+        eprintln!("quant size: {:>8.2}", new_size as f32 / 1024.0 / 1024.0);
+
+        let sum_all: i64 = histogram.iter().sum();
+        eprint!("hist: ");
+        for hist in histogram {
+            eprint!("{:>5.3} ", *hist as f32 / sum_all as f32);
+        }
+        eprintln!();
+
+        eprint!("by type: ");
+        for (ftype, size) in size_by_ftype.iter().enumerate() {
+            if *size > 0 {
+                eprint!("{}={:.2}MiB ", FTYPE_STR[ftype], *size as f32 / 1024.0 / 1024.0);
+            }
+        }
+        eprintln!();
+    }
+}
+
+/// Serializes [`TensorResult`]s to `fout` in strict file order, shared
+/// across the worker pool behind a [`Mutex`] so at most one thread is
+/// writing (and accumulating totals) at a time. Results that finish out of
+/// order are held in `pending` until the gap before them closes.
+struct TensorWriter<'a> {
+    fout: &'a mut BufWriter<File>,
+    reporter: &'a mut (dyn QuantizeReporter + Send),
+    compress: bool,
+    checksum: bool,
+    pending: BTreeMap<usize, TensorResult>,
+    next_index: usize,
+    bytes_done: u64,
+    bytes_total: u64,
+    total_size_org: usize,
+    total_size_new: usize,
+    hist_all: Vec<i64>,
+    size_by_ftype: [usize; FTYPE_STR.len()],
+    checksums: Vec<TensorChecksum>,
+}
+
+impl<'a> TensorWriter<'a> {
+    /// Buffers `result`, then writes out everything now available in file
+    /// order, draining `pending` as far as the run of consecutive indices
+    /// reaches.
+    fn enqueue(&mut self, result: TensorResult) -> Result<(), LoadError> {
+        self.pending.insert(result.index, result);
+        while let Some(result) = self.pending.remove(&self.next_index) {
+            self.write_one(result)?;
+            self.next_index += 1;
+        }
+        Ok(())
+    }
+
+    fn write_one(&mut self, result: TensorResult) -> Result<(), LoadError> {
+        self.reporter.on_tensor(
+            &result.name,
+            &result.ne[..result.n_dims as usize],
+            result.src_ftype,
+            result.ftype,
+        );
+
+        self.fout
+            .write_all(&result.n_dims.to_le_bytes())
+            .map_err(LoadError::Io)?;
+        self.fout
+            .write_all(&(result.name.len() as i32).to_le_bytes())
+            .map_err(LoadError::Io)?;
+        self.fout
+            .write_all(&(result.ftype as i32).to_le_bytes())
+            .map_err(LoadError::Io)?;
+        for i in 0..result.n_dims {
+            self.fout
+                .write_all(&result.ne[i as usize].to_le_bytes())
+                .map_err(LoadError::Io)?;
+        }
+        self.fout.write_all(result.name.as_bytes()).map_err(LoadError::Io)?;
+
+        if self.compress {
+            let compressed = zstd::encode_all(&result.payload[..], 0).map_err(LoadError::Io)?;
+            self.fout
+                .write_all(&(compressed.len() as u32).to_le_bytes())
+                .map_err(LoadError::Io)?;
+            self.fout
+                .write_all(&(result.payload.len() as u32).to_le_bytes())
+                .map_err(LoadError::Io)?;
+            self.fout.write_all(&compressed).map_err(LoadError::Io)?;
+            self.total_size_new += compressed.len();
+        } else {
+            self.fout.write_all(&result.payload).map_err(LoadError::Io)?;
+            self.total_size_new += result.payload.len();
+        }
+        self.size_by_ftype[result.ftype] += result.payload.len();
+
+        if result.ftype != result.src_ftype {
+            for (i, val) in result.hist.iter().enumerate() {
+                self.hist_all[i] += val;
+            }
+        }
+
+        if self.checksum {
+            self.checksums.push(TensorChecksum {
+                name: result.name.clone(),
+                payload_len: result.payload.len() as u64,
+                crc32: crc32fast::hash(&result.payload),
+                max_abs_err: result.max_abs_err,
+                mean_abs_err: result.mean_abs_err,
+            });
+        }
+
+        self.total_size_org += (result.nelements * 4) as usize;
+        let src_bpe = if result.src_ftype == 0 { 4 } else { 2 };
+        self.bytes_done += (result.nelements * src_bpe) as u64;
+        self.reporter.on_progress(self.bytes_done, self.bytes_total);
+        Ok(())
+    }
+}
+
+/// Quantizes the model at `file_name_in` into `file_name_out` according to
+/// `policy`, optionally appending a checksum trailer (`checksum`, see
+/// [`llama_model_verify`]) and compressing each tensor independently with
+/// zstd (`compress`, see [`FILE_MAGIC_GGMC`]).
+///
+/// The resulting GGMC file can't be loaded back by
+/// `ggml-format::load_model_from_reader`; load it with
+/// `ggml-format::load_model_from_ggmc_reader` instead, which streams and
+/// decompresses it tensor-by-tensor (see [`FILE_MAGIC_GGMC`]).
+///
+/// Tensors are streamed off the source file and quantized in a rayon
+/// worker pool one at a time (see [`TensorWriter`]), so peak memory stays
+/// around a handful of tensors' worth of data rather than the whole model
+/// twice over (source plus output).
 pub fn llama_model_quantize(
     file_name_in: impl AsRef<Path>,
     file_name_out: impl AsRef<Path>,
-    itype: u8,
+    policy: &QuantizePolicy,
     qk: u8,
+    checksum: bool,
+    compress: bool,
+    reporter: &mut (dyn QuantizeReporter + Send),
 ) -> Result<bool, LoadError> {
-    let mut otype = TYPE_Q4_1;
-
-    match itype {
-        2 => otype = TYPE_Q4_0,
-        3 => otype = TYPE_Q4_1,
-        _ => {
-            return Err(LoadError::InvalidItype(itype));
-        }
-    };
+    let itype = policy.header_ftype();
 
     let file_in = file_name_in.as_ref();
     let mut finp = BufReader::new(File::open(file_in).map_err(|e| LoadError::OpenFileFailed {
@@ -45,7 +459,7 @@ pub fn llama_model_quantize(
     // Verify magic
     {
         let mut magic_buffer: [u8; 4] = [0; 4];
-        finp.read_exact(&mut magic_buffer).unwrap();
+        finp.read_exact(&mut magic_buffer).map_err(LoadError::Io)?;
 
         let magic = u32::from_le_bytes(magic_buffer);
         if magic == FILE_MAGIC_UNVERSIONED {
@@ -57,10 +471,14 @@ pub fn llama_model_quantize(
             });
         }
 
-        fout.write(&magic_buffer).unwrap();
+        if compress {
+            fout.write_all(&FILE_MAGIC_GGMC.to_le_bytes()).map_err(LoadError::Io)?;
+        } else {
+            fout.write_all(&magic_buffer).map_err(LoadError::Io)?;
+        }
 
         let mut version_buffer: [u8; 4] = [0; 4];
-        finp.read_exact(&mut version_buffer).unwrap();
+        finp.read_exact(&mut version_buffer).map_err(LoadError::Io)?;
 
         let format_version = u32::from_le_bytes(version_buffer);
 
@@ -70,7 +488,7 @@ pub fn llama_model_quantize(
             });
         }
 
-        fout.write(&version_buffer).unwrap();
+        fout.write_all(&version_buffer).map_err(LoadError::Io)?;
     }
 
     let mut hparams = Hyperparameters::default();
@@ -78,40 +496,33 @@ pub fn llama_model_quantize(
     // Load parameters
     {
         let mut buffer: [u8; 4] = [0; 4];
-        finp.read_exact(&mut buffer).unwrap();
+        finp.read_exact(&mut buffer).map_err(LoadError::Io)?;
         hparams.n_vocab = i32::from_le_bytes(buffer);
-        println!("n_vocab: {}", hparams.n_vocab);
-        fout.write(&buffer).unwrap();
+        fout.write_all(&buffer).map_err(LoadError::Io)?;
 
-        finp.read_exact(&mut buffer).unwrap();
+        finp.read_exact(&mut buffer).map_err(LoadError::Io)?;
         hparams.n_embd = i32::from_le_bytes(buffer);
-        println!("n_embd: {}", hparams.n_embd);
-        fout.write(&buffer).unwrap();
+        fout.write_all(&buffer).map_err(LoadError::Io)?;
 
-        finp.read_exact(&mut buffer).unwrap();
+        finp.read_exact(&mut buffer).map_err(LoadError::Io)?;
         hparams.n_mult = i32::from_le_bytes(buffer);
-        println!("n_mult: {}", hparams.n_mult);
-        fout.write(&buffer).unwrap();
+        fout.write_all(&buffer).map_err(LoadError::Io)?;
 
-        finp.read_exact(&mut buffer).unwrap();
+        finp.read_exact(&mut buffer).map_err(LoadError::Io)?;
         hparams.n_head = i32::from_le_bytes(buffer);
-        println!("n_head: {}", hparams.n_head);
-        fout.write(&buffer).unwrap();
+        fout.write_all(&buffer).map_err(LoadError::Io)?;
 
-        finp.read_exact(&mut buffer).unwrap();
+        finp.read_exact(&mut buffer).map_err(LoadError::Io)?;
         hparams.n_layer = i32::from_le_bytes(buffer);
-        println!("n_layer: {}", hparams.n_layer);
-        fout.write(&buffer).unwrap();
+        fout.write_all(&buffer).map_err(LoadError::Io)?;
 
-        finp.read_exact(&mut buffer).unwrap();
+        finp.read_exact(&mut buffer).map_err(LoadError::Io)?;
         hparams.n_rot = i32::from_le_bytes(buffer);
-        println!("n_rot: {}", hparams.n_rot);
-        fout.write(&buffer).unwrap();
+        fout.write_all(&buffer).map_err(LoadError::Io)?;
 
-        finp.read_exact(&mut buffer).unwrap();
+        finp.read_exact(&mut buffer).map_err(LoadError::Io)?;
         hparams.f16_ = i32::from_le_bytes(buffer);
-        println!("f16_: {}", hparams.f16_);
-        fout.write(&(itype as i32).to_le_bytes()).unwrap();
+        fout.write_all(&(itype as i32).to_le_bytes()).map_err(LoadError::Io)?;
     }
 
     // load vocab
@@ -127,19 +538,19 @@ pub fn llama_model_quantize(
 
         for i in 0..n_vocab {
             let mut len_buffer = [0u8; 4];
-            finp.read_exact(&mut len_buffer).unwrap();
-            fout.write(&len_buffer).unwrap();
+            finp.read_exact(&mut len_buffer).map_err(LoadError::Io)?;
+            fout.write_all(&len_buffer).map_err(LoadError::Io)?;
             let len = u32::from_le_bytes(len_buffer) as usize;
 
             let mut word_buffer = vec![0u8; len];
-            finp.read_exact(word_buffer.as_mut_slice()).unwrap();
-            fout.write(&word_buffer).unwrap();
+            finp.read_exact(word_buffer.as_mut_slice()).map_err(LoadError::Io)?;
+            fout.write_all(&word_buffer).map_err(LoadError::Io)?;
 
             let word = String::from_utf8_lossy(&word_buffer).to_string();
 
             let mut score_buffer = [0u8; 4];
-            finp.read_exact(&mut score_buffer).unwrap();
-            fout.write(&score_buffer).unwrap();
+            finp.read_exact(&mut score_buffer).map_err(LoadError::Io)?;
+            fout.write_all(&score_buffer).map_err(LoadError::Io)?;
             let score = f32::from_le_bytes(score_buffer);
 
             vocab.token_to_id.insert(word.clone(), i);
@@ -151,193 +562,382 @@ pub fn llama_model_quantize(
 
     // Load weights
     {
-        let mut total_size_org: usize = 0;
-        let mut total_size_new: usize = 0;
-
-        let mut work: Vec<f32> = vec![];
-
-        let mut data_u8: Vec<u8> = vec![];
-        let mut data_f16: Vec<u16> = vec![];
-        let mut data_f32: Vec<f32> = vec![];
-
-        let mut hist_all: Vec<i64> = vec![0; 16];
-
-        loop {
+        // Upper bound on the bytes left to read, used only as the
+        // denominator for `on_progress`; computed up front from the file's
+        // length so we don't have to read every tensor before quantizing
+        // the first one.
+        let bytes_total = finp
+            .get_ref()
+            .metadata()
+            .map_err(LoadError::Io)?
+            .len()
+            .saturating_sub(finp.stream_position().map_err(LoadError::Io)?);
+
+        let mut next_job_index = 0usize;
+        // Reads one tensor header plus its raw data off `finp` at a time.
+        // `finp` isn't seekable/shareable across threads, so this stays a
+        // single-threaded producer; `.par_bridge()` below is what lets the
+        // (expensive) quantization of each tensor it yields run in a worker
+        // pool instead of after every tensor has already been read into
+        // memory.
+        let job_iter = std::iter::from_fn(move || -> Option<Result<TensorJob, LoadError>> {
             let mut buffer = [0u8; 4];
-            if finp.read_exact(&mut buffer).is_err() {
-                break;
-            };
+
+            // Only a genuine 0-byte read right at a tensor boundary is a
+            // clean end of the tensor list; a `read_exact` failure here
+            // (e.g. the file is truncated 2 bytes into `n_dims`) must
+            // surface as an error instead of being mistaken for EOF.
+            match finp.fill_buf() {
+                Ok(buf) if buf.is_empty() => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(LoadError::Io(e))),
+            }
+            if let Err(e) = finp.read_exact(&mut buffer) {
+                return Some(Err(LoadError::Io(e)));
+            }
             let n_dims = i32::from_le_bytes(buffer);
 
-            if finp.read_exact(&mut buffer).is_err() {
-                break;
-            };
+            if let Err(e) = finp.read_exact(&mut buffer) {
+                return Some(Err(LoadError::Io(e)));
+            }
             let length = i32::from_le_bytes(buffer) as usize;
 
-            if finp.read_exact(&mut buffer).is_err() {
-                break;
-            };
-            let mut ftype = i32::from_le_bytes(buffer) as usize;
-
-            println!("n_dims: {}, length: {}, ftype: {} ", n_dims, length, ftype);
+            if let Err(e) = finp.read_exact(&mut buffer) {
+                return Some(Err(LoadError::Io(e)));
+            }
+            let src_ftype = i32::from_le_bytes(buffer) as usize;
 
             let mut nelements = 1i32;
             let mut ne = [1i32, 1i32];
             for i in 0..n_dims {
-                finp.read_exact(&mut buffer).unwrap();
+                if let Err(e) = finp.read_exact(&mut buffer) {
+                    return Some(Err(LoadError::Io(e)));
+                }
                 ne[i as usize] = i32::from_le_bytes(buffer);
                 nelements *= ne[i as usize];
             }
 
             let mut name_buffer = vec![0u8; length];
-            finp.read_exact(&mut name_buffer).unwrap();
-            let name = String::from_utf8(name_buffer).unwrap();
-            println!("Nelements: {}", nelements);
-            print!(
-                "{:>48} - [{:>5}, {:>5}], type = {:>6}",
-                format!("'{}'", name),
-                ne[0],
-                ne[1],
-                FTYPE_STR[ftype]
-            );
+            if let Err(e) = finp.read_exact(&mut name_buffer) {
+                return Some(Err(LoadError::Io(e)));
+            }
+            let name = match String::from_utf8(name_buffer) {
+                Ok(name) => name,
+                Err(e) => {
+                    return Some(Err(LoadError::InvalidUtf8Name {
+                        source: e,
+                        path: file_in.to_owned(),
+                    }))
+                }
+            };
 
-            // Quantize only 2D tensors
-            let mut quantize = name.find("weight").is_some() && n_dims == 2;
+            let target = policy.target_for(&name, n_dims);
+            let quantize = target != QuantizeTarget::Unquantized;
+
+            let mut data_f32 = vec![];
+            let mut data_u8 = vec![];
 
             if quantize {
-                if ftype != 0 && ftype != 1 {
-                    return Err(LoadError::InvalidFtype {
-                        ftype: ftype as i32,
+                if src_ftype != 0 && src_ftype != 1 {
+                    return Some(Err(LoadError::InvalidFtype {
+                        ftype: src_ftype as i32,
                         path: file_in.to_owned(),
-                    });
+                    }));
                 }
 
                 data_f32.resize(nelements as usize, 0.0);
-                if ftype == 1 {
-                    data_f16.resize(nelements as usize, 0);
-
+                if src_ftype == 1 {
                     let mut buffer = vec![0u8; (nelements * 2) as usize];
-                    finp.read_exact(&mut buffer).unwrap();
-                    // Compute buffer
+                    if let Err(e) = finp.read_exact(&mut buffer) {
+                        return Some(Err(LoadError::Io(e)));
+                    }
                     for (index, chunk) in buffer.chunks(2).enumerate() {
                         let i = u16::from_le_bytes([chunk[0], chunk[1]]);
-                        data_f16[index] = i;
-
-                        //data_f32[index] = ggml_fp16_to_fp32(i);
                         data_f32[index] = f16::from_bits(i).to_f32();
                     }
                 } else {
                     let mut buffer = vec![0u8; (nelements * 4) as usize];
-                    finp.read_exact(&mut buffer).unwrap();
+                    if let Err(e) = finp.read_exact(&mut buffer) {
+                        return Some(Err(LoadError::Io(e)));
+                    }
 
                     for (index, chunk) in buffer.chunks(4).enumerate() {
                         data_f32[index] =
                             f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
                     }
                 }
-
-                ftype = itype as usize;
             } else {
                 // Determines the total bytes were dealing with
-                let bpe = (nelements * if ftype == 0 { 4 } else { 2 }) as usize;
+                let bpe = (nelements * if src_ftype == 0 { 4 } else { 2 }) as usize;
 
                 data_u8.resize(bpe, 0);
-                finp.read_exact(&mut data_u8).unwrap();
+                if let Err(e) = finp.read_exact(&mut data_u8) {
+                    return Some(Err(LoadError::Io(e)));
+                }
             }
 
-            // Write data
-            fout.write(&n_dims.to_le_bytes()).unwrap();
-            fout.write(&(length as i32).to_le_bytes()).unwrap();
-            println!(" new ftype: {}", ftype);
-            println!("{:?}", name.as_bytes());
-            fout.write(&(ftype as i32).to_le_bytes()).unwrap();
+            let index = next_job_index;
+            next_job_index += 1;
+
+            Some(Ok(TensorJob {
+                index,
+                name,
+                n_dims,
+                ne,
+                nelements,
+                src_ftype,
+                target,
+                data_f32,
+                data_u8,
+            }))
+        });
+
+        let writer = Mutex::new(TensorWriter {
+            fout: &mut fout,
+            reporter,
+            compress,
+            checksum,
+            pending: BTreeMap::new(),
+            next_index: 0,
+            bytes_done: 0,
+            bytes_total,
+            total_size_org: 0,
+            total_size_new: 0,
+            hist_all: vec![0; 16],
+            size_by_ftype: [0; FTYPE_STR.len()],
+            checksums: vec![],
+        });
+
+        job_iter
+            .par_bridge()
+            .try_for_each(|job| -> Result<(), LoadError> {
+                let job = job?;
+                let result = quantize_job(job, qk).map_err(|_| LoadError::InvalidFtype {
+                    ftype: itype as i32,
+                    path: file_out.to_owned(),
+                })?;
+                writer.lock().unwrap().enqueue(result)
+            })?;
+
+        let TensorWriter {
+            total_size_org,
+            total_size_new,
+            hist_all,
+            size_by_ftype,
+            checksums,
+            reporter,
+            ..
+        } = writer.into_inner().unwrap();
+
+        if checksum {
+            write_checksum_trailer(&mut fout, &checksums).map_err(|e| LoadError::CreateFileFailed {
+                source: e,
+                path: file_out.to_owned(),
+            })?;
+        }
 
-            for i in 0..n_dims {
-                fout.write(&ne[i as usize].to_le_bytes()).unwrap();
-            }
-            fout.write(name.as_bytes()).unwrap();
+        reporter.on_finished(
+            total_size_org as u64,
+            total_size_new as u64,
+            &hist_all,
+            &size_by_ftype,
+        );
+    }
 
-            if quantize {
-                print!("quantizing .. ");
-                work.resize(nelements as usize, 0.0);
-
-                let curr_size;
-                let mut hist_cur = vec![0; 16];
-
-                match otype {
-                    TYPE_Q4_0 => {
-                        curr_size = quantize_q4_0(
-                            &mut data_f32,
-                            &mut work,
-                            nelements,
-                            ne[0],
-                            qk as i32,
-                            &mut hist_cur,
-                        )
-                    }
-                    TYPE_Q4_1 => {
-                        curr_size = quantize_q4_1(
-                            &mut data_f32,
-                            &mut work,
-                            nelements,
-                            ne[0],
-                            qk as i32,
-                            &mut hist_cur,
-                        )
-                    }
-                    _ => {
-                        println!("Unsupported type");
-                        return Ok(false);
-                    }
-                }
+    return Ok(true);
+}
 
-                // We divide curr size by 4
-                for i in 0..curr_size / 4 {
-                    fout.write(&work[i].to_le_bytes()).unwrap();
-                }
+/// Writes the checksum trailer to `fout`: per-tensor integrity data followed
+/// by a whole-file digest, with a leading magic and a trailing byte length
+/// so [`llama_model_verify`] can find and parse it without needing to
+/// understand the tensor headers that precede it.
+fn write_checksum_trailer(
+    fout: &mut BufWriter<File>,
+    checksums: &[TensorChecksum],
+) -> std::io::Result<()> {
+    let mut trailer = Vec::new();
+    trailer.extend_from_slice(&CHECKSUM_TRAILER_MAGIC.to_le_bytes());
+    trailer.extend_from_slice(&(checksums.len() as u32).to_le_bytes());
+
+    let mut whole_file_hasher = crc32fast::Hasher::new();
+    for t in checksums {
+        trailer.extend_from_slice(&(t.name.len() as u32).to_le_bytes());
+        trailer.extend_from_slice(t.name.as_bytes());
+        trailer.extend_from_slice(&t.payload_len.to_le_bytes());
+        trailer.extend_from_slice(&t.crc32.to_le_bytes());
+        trailer.extend_from_slice(&t.max_abs_err.to_le_bytes());
+        trailer.extend_from_slice(&t.mean_abs_err.to_le_bytes());
+        whole_file_hasher.update(&t.crc32.to_le_bytes());
+    }
+    trailer.extend_from_slice(&whole_file_hasher.finalize().to_le_bytes());
 
-                total_size_new += curr_size;
+    fout.write_all(&trailer)?;
+    fout.write_all(&(trailer.len() as u64).to_le_bytes())?;
+    Ok(())
+}
 
-                print!(
-                    "size = {:>8.2} MB -> {:>8.2} MB | hist: ",
-                    nelements as f32 * 4.0 / 1024.0 / 1024.0,
-                    curr_size as f32 / 1024.0 / 1024.0
-                );
+/// Reads the checksum trailer written by [`write_checksum_trailer`] from the
+/// tail of `f`, restoring the reader's position to the start of the file
+/// afterwards so the caller can walk the tensor data from the beginning.
+/// Returns the per-tensor entries alongside the whole-file digest that
+/// followed them, which the caller is responsible for recomputing and
+/// checking against (this function only parses the trailer, it doesn't
+/// verify anything).
+fn read_checksum_trailer(
+    f: &mut BufReader<File>,
+) -> Result<(Vec<TensorChecksum>, u32), LoadError> {
+    let file_len = f.seek(SeekFrom::End(0)).map_err(LoadError::Io)?;
+
+    let mut len_buffer = [0u8; 8];
+    f.seek(SeekFrom::End(-8)).map_err(LoadError::Io)?;
+    f.read_exact(&mut len_buffer).map_err(LoadError::Io)?;
+    let trailer_len = u64::from_le_bytes(len_buffer);
+
+    let trailer_start = file_len
+        .checked_sub(8 + trailer_len)
+        .ok_or(LoadError::MissingChecksumTrailer)?;
+    f.seek(SeekFrom::Start(trailer_start)).map_err(LoadError::Io)?;
+
+    let mut buffer = [0u8; 4];
+    f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+    if u32::from_le_bytes(buffer) != CHECKSUM_TRAILER_MAGIC {
+        return Err(LoadError::MissingChecksumTrailer);
+    }
 
-                for (i, val) in hist_cur.iter().enumerate() {
-                    hist_all[i] += val;
-                    print!("{:>5.3} ", *val as f32 / nelements as f32);
-                }
-                println!();
-            } else {
-                fout.write(&data_u8).unwrap();
-                println!("size = {:>8.3} MB", data_u8.len() as f64 / 1024.0 / 1024.0);
-                total_size_new += data_u8.len();
-            }
+    f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+    let count = u32::from_le_bytes(buffer);
+
+    let mut checksums = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+        let name_len = u32::from_le_bytes(buffer) as usize;
+        let mut name_buffer = vec![0u8; name_len];
+        f.read_exact(&mut name_buffer).map_err(LoadError::Io)?;
+        let name = String::from_utf8_lossy(&name_buffer).to_string();
+
+        let mut len_buffer = [0u8; 8];
+        f.read_exact(&mut len_buffer).map_err(LoadError::Io)?;
+        let payload_len = u64::from_le_bytes(len_buffer);
+
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+        let crc32 = u32::from_le_bytes(buffer);
+
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+        let max_abs_err = f32::from_le_bytes(buffer);
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+        let mean_abs_err = f32::from_le_bytes(buffer);
+
+        checksums.push(TensorChecksum {
+            name,
+            payload_len,
+            crc32,
+            max_abs_err,
+            mean_abs_err,
+        });
+    }
 
-            total_size_org += (nelements * 4) as usize;
-        }
+    f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+    let whole_file_crc32 = u32::from_le_bytes(buffer);
 
-        println!(
-            "model size: {:>8.2}",
-            total_size_org as f32 / 1024.0 / 1024.0
-        );
+    f.seek(SeekFrom::Start(0)).map_err(LoadError::Io)?;
+    Ok((checksums, whole_file_crc32))
+}
 
-        println!(
-            "quant size: {:>8.2}",
-            total_size_new as f32 / 1024.0 / 1024.0
-        );
+/// Re-reads a model quantized with `checksum: true` and confirms every
+/// tensor's recorded checksum still matches its bytes on disk, then confirms
+/// the trailer's own whole-file digest against a digest recomputed the same
+/// way [`write_checksum_trailer`] built it (a rolling CRC32 over each
+/// tensor's recorded `crc32`, in file order). Returns `Ok(false)` (without
+/// erroring) on the first mismatch found, whether per-tensor or whole-file.
+///
+/// This only checks data integrity (has the payload been corrupted or
+/// truncated since quantization), not quantization quality: the trailer's
+/// `max_abs_err`/`mean_abs_err` were measured against the *original*
+/// unquantized tensors at quantize time, and `llama_model_verify` never has
+/// those original values to re-derive them against, so it doesn't attempt
+/// to recompute or report them.
+pub fn llama_model_verify(file_name: impl AsRef<Path>) -> Result<bool, LoadError> {
+    let file = file_name.as_ref();
+    let mut f = BufReader::new(File::open(file).map_err(|e| LoadError::OpenFileFailed {
+        source: e,
+        path: file.to_owned(),
+    })?);
 
-        {
-            let sum_all: i64 = hist_all.iter().sum();
+    let (checksums, expected_whole_file_crc32) = read_checksum_trailer(&mut f)?;
+    let mut whole_file_hasher = crc32fast::Hasher::new();
+
+    // Header is magic, version, then 7 i32 hyperparameters (n_vocab is the
+    // first of them); skip straight to the vocabulary.
+    let mut buffer = [0u8; 4];
+    f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+    let compressed = u32::from_le_bytes(buffer) == FILE_MAGIC_GGMC;
+    f.read_exact(&mut buffer).map_err(LoadError::Io)?; // version
+    f.read_exact(&mut buffer).map_err(LoadError::Io)?; // n_vocab
+    let n_vocab = i32::from_le_bytes(buffer);
+    for _ in 0..6 {
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?; // n_embd, n_mult, n_head, n_layer, n_rot, itype
+    }
 
-            print!("hist: ");
-            for hist in hist_all {
-                print!("{:>5.3} ", hist as f32 / sum_all as f32);
-            }
-            println!();
+    // Skip the vocabulary.
+    for _ in 0..n_vocab {
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+        let len = u32::from_le_bytes(buffer) as usize;
+        let mut word_buffer = vec![0u8; len];
+        f.read_exact(&mut word_buffer).map_err(LoadError::Io)?;
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?; // score
+    }
+
+    // Walk the tensors in file order, using the trailer's recorded lengths
+    // to know how many payload bytes belong to each one.
+    for expected in &checksums {
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?; // n_dims
+        let n_dims = i32::from_le_bytes(buffer);
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?; // name_len
+        let name_len = u32::from_le_bytes(buffer) as usize;
+        f.read_exact(&mut buffer).map_err(LoadError::Io)?; // ftype
+
+        for _ in 0..n_dims {
+            f.read_exact(&mut buffer).map_err(LoadError::Io)?;
+        }
+        let mut name_buffer = vec![0u8; name_len];
+        f.read_exact(&mut name_buffer).map_err(LoadError::Io)?;
+        let name = String::from_utf8_lossy(&name_buffer).to_string();
+
+        let payload = if compressed {
+            f.read_exact(&mut buffer).map_err(LoadError::Io)?; // compressed_len
+            let compressed_len = u32::from_le_bytes(buffer) as usize;
+            f.read_exact(&mut buffer).map_err(LoadError::Io)?; // uncompressed_len
+            let uncompressed_len = u32::from_le_bytes(buffer) as usize;
+
+            let mut compressed_buf = vec![0u8; compressed_len];
+            f.read_exact(&mut compressed_buf).map_err(LoadError::Io)?;
+            let payload = zstd::decode_all(&compressed_buf[..]).map_err(LoadError::Io)?;
+            debug_assert_eq!(payload.len(), uncompressed_len);
+            payload
+        } else {
+            let mut payload = vec![0u8; expected.payload_len as usize];
+            f.read_exact(&mut payload).map_err(LoadError::Io)?;
+            payload
+        };
+        let actual_crc32 = crc32fast::hash(&payload);
+
+        if name != expected.name || actual_crc32 != expected.crc32 {
+            eprintln!(
+                "checksum mismatch for tensor {name:?}: expected {:x}, got {actual_crc32:x}",
+                expected.crc32
+            );
+            return Ok(false);
         }
+        whole_file_hasher.update(&expected.crc32.to_le_bytes());
     }
 
-    return Ok(true);
+    let actual_whole_file_crc32 = whole_file_hasher.finalize();
+    if actual_whole_file_crc32 != expected_whole_file_crc32 {
+        eprintln!(
+            "whole-file checksum mismatch: expected {expected_whole_file_crc32:x}, got {actual_whole_file_crc32:x}"
+        );
+        return Ok(false);
+    }
+
+    Ok(true)
 }